@@ -1,12 +1,20 @@
 use napi::bindgen_prelude::*;
 use napi_derive::napi;
 use grep::{
-    matcher::{Matcher, Match},
+    matcher::{Captures, Matcher, Match},
     regex::RegexMatcherBuilder,
-    searcher::{Searcher, SearcherBuilder, Sink, SinkMatch},
+    searcher::{
+        BinaryDetection, Encoding, MmapChoice, Searcher, SearcherBuilder, Sink, SinkContext,
+        SinkContextKind, SinkFinish, SinkMatch,
+    },
 };
-use grep_searcher::{SearcherBuilder as GrepSearcherBuilder, lines};
+#[cfg(feature = "pcre2")]
+use grep_pcre2::RegexMatcherBuilder as Pcre2MatcherBuilder;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::{WalkBuilder, WalkState};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc;
 use std::sync::{Arc, Mutex};
 
 /// Ripgrep search result match
@@ -17,6 +25,10 @@ pub struct RipgrepMatch {
     pub column: u32,
     pub text: String,
     pub submatches: Vec<SubMatch>,
+    /// Lines immediately preceding the match, requested via `before_context`.
+    pub context_before: Vec<ContextLine>,
+    /// Lines immediately following the match, requested via `after_context`.
+    pub context_after: Vec<ContextLine>,
 }
 
 /// Submatch within a line
@@ -26,19 +38,66 @@ pub struct SubMatch {
     pub end: u32,
 }
 
+/// A non-matching line surrounding a match, returned for `grep -C`-style context
+#[napi(object)]
+pub struct ContextLine {
+    pub line_number: u32,
+    pub text: String,
+}
+
 /// Search options
 #[napi(object)]
+#[derive(Clone)]
 pub struct RipgrepOptions {
     pub pattern: String,
     pub path: String,
     pub case_insensitive: Option<bool>,
-    pub glob: Option<String>,
+    /// Shell glob patterns to scope the search, matched against the full
+    /// path (not just the file name). A pattern prefixed with `!` excludes
+    /// matching paths instead of including them, e.g. `["*.rs", "!**/tests/**"]`.
+    pub globs: Option<Vec<String>>,
     pub max_depth: Option<u32>,
     pub max_results: Option<u32>,
     pub before_context: Option<u32>,
     pub after_context: Option<u32>,
     pub files_with_matches: Option<bool>,
     pub include_hidden: Option<bool>,
+    /// Number of worker threads to walk and search with. 0 or unset uses
+    /// the number of logical CPUs, matching ripgrep's own default.
+    pub threads: Option<u32>,
+    /// Use the PCRE2 engine instead of Rust's `regex` crate, enabling
+    /// lookaround and backreferences. Requires the `pcre2` cargo feature.
+    pub pcre2: Option<bool>,
+    /// Let `^`/`$` match at line boundaries, with either regex engine.
+    pub multi_line: Option<bool>,
+    /// Only search files recognized as one of these types, e.g. `["rust", "js"]`.
+    /// See `list_types()` for the full set of built-in definitions.
+    pub types: Option<Vec<String>>,
+    /// Exclude files recognized as one of these types.
+    pub types_not: Option<Vec<String>>,
+    /// Extra `name:glob` definitions, e.g. `["proto:*.proto"]`, layered on
+    /// top of the built-in type definitions before `types`/`types_not` are
+    /// applied.
+    pub type_defs: Option<Vec<String>>,
+    /// Text encoding of searched files, e.g. `"utf-16"` or `"latin1"`.
+    /// Unset auto-detects via BOM sniffing and otherwise assumes UTF-8.
+    pub encoding: Option<String>,
+    /// How to handle files containing a NUL byte: `"none"` searches them as
+    /// text (the default, may mangle output), `"quit"` skips them entirely,
+    /// `"convert"` replaces NUL bytes with line terminators so the rest of
+    /// the file still searches.
+    pub binary_detection: Option<String>,
+    /// Memory-map files before searching when it's safe to do so. Defaults
+    /// to ripgrep's own heuristic (`MmapChoice::auto`); `false` forces
+    /// regular reads.
+    pub mmap: Option<bool>,
+}
+
+/// A named file-type definition as understood by `--type`/`--type-not`.
+#[napi(object)]
+pub struct TypeDef {
+    pub name: String,
+    pub globs: Vec<String>,
 }
 
 /// Search result
@@ -49,15 +108,13 @@ pub struct RipgrepResult {
     pub total_matches: u32,
     pub truncated: bool,
     pub elapsed_ms: u32,
+    /// Files excluded by the binary-detection heuristic (`binary_detection: "quit"`).
+    pub binary_skipped: u32,
 }
 
 /// Ripgrep search state
 #[napi]
 pub struct RipgrepSearcher {
-    matcher: Arc<RegexMatcherBuilder>,
-    searcher: Arc<Mutex<Searcher>>,
-    results: Arc<Mutex<Vec<RipgrepMatch>>>,
-    files_searched: Arc<Mutex<u32>>,
     max_results: Option<u32>,
 }
 
@@ -66,105 +123,183 @@ impl RipgrepSearcher {
     /// Create a new ripgrep searcher
     #[napi(constructor)]
     pub fn new(opts: RipgrepOptions) -> Result<Self> {
-        let mut builder = RegexMatcherBuilder::new();
-        builder.case_insensitive(opts.case_insensitive.unwrap_or(false));
-        
-        let matcher = Arc::new(builder);
-        let searcher = Arc::new(Mutex::new(SearcherBuilder::new()
-            .before_context(opts.before_context.unwrap_or(0))
-            .after_context(opts.after_context.unwrap_or(0))
-            .build()));
-        let results = Arc::new(Mutex::new(Vec::new()));
-        let files_searched = Arc::new(Mutex::new(0));
-        
         Ok(Self {
-            matcher,
-            searcher,
-            results,
-            files_searched,
             max_results: opts.max_results,
         })
     }
 
-    /// Search for pattern in path
+    /// Search for pattern in path, walking and matching across a thread pool
     #[napi]
     pub async fn search(&self, opts: RipgrepOptions) -> Result<RipgrepResult> {
         let start = std::time::Instant::now();
-        
-        // Clear previous results
-        {
-            let mut results = self.results.lock().unwrap();
-            results.clear();
-            let mut files = self.files_searched.lock().unwrap();
-            *files = 0;
-        }
 
-        // Build walker
-        let mut walker_builder = ignore::WalkBuilder::new(&opts.path);
+        let threads = match opts.threads {
+            Some(0) | None => num_cpus(),
+            Some(n) => n as usize,
+        };
+
+        let mut walker_builder = WalkBuilder::new(&opts.path);
         walker_builder
             .hidden(!opts.include_hidden.unwrap_or(false))
             .git_ignore(true)
-            .max_depth(opts.max_depth.map(|d| d as usize));
+            .max_depth(opts.max_depth.map(|d| d as usize))
+            .threads(threads);
 
-        if let Some(glob) = &opts.glob {
+        let globs = opts.globs.clone().unwrap_or_default();
+        if !globs.is_empty() {
             walker_builder.add_custom_ignore_filename(".rgignore");
         }
+        let glob_filter = Arc::new(
+            GlobFilter::build(&globs)
+                .map_err(|e| Error::from_reason(format!("Invalid glob pattern: {}", e)))?,
+        );
 
-        let walker = walker_builder.build();
-
-        // Search each entry
-        for entry in walker {
-            if let Ok(entry) = entry {
-                if entry.file_type().map_or(false, |ft| ft.is_file()) {
-                    let path = entry.path().to_path_buf();
-                    
-                    // Check glob pattern
-                    if let Some(glob) = &opts.glob {
-                        if let Some(name) = path.file_name() {
-                            if !glob_match(glob, name.to_string_lossy().as_ref()) {
-                                continue;
-                            }
-                        }
-                    }
+        let types = build_types(
+            opts.types.as_deref().unwrap_or(&[]),
+            opts.types_not.as_deref().unwrap_or(&[]),
+            opts.type_defs.as_deref().unwrap_or(&[]),
+        )
+        .map_err(|e| Error::from_reason(format!("Invalid file type filter: {}", e)))?;
+        walker_builder.types(types);
 
-                    // Search file
-                    let mut searcher = self.searcher.lock().unwrap();
-                    let matcher = self.matcher.build(&opts.pattern).map_err(|e| Error::from_reason(format!("Regex error: {}", e)))?;
-                    
-                    let mut sink = MatchSink {
-                        path: path.clone(),
-                        results: self.results.clone(),
-                        max_results: self.max_results,
-                        stopped: false,
-                    };
-
-                    let _ = searcher.search_path(&matcher, &path, &mut sink);
-                    
-                    {
-                        let mut files = self.files_searched.lock().unwrap();
-                        *files += 1;
-                    }
+        let max_results = self.max_results;
+        let pattern = opts.pattern.clone();
+        let case_insensitive = opts.case_insensitive.unwrap_or(false);
+        let multi_line = opts.multi_line.unwrap_or(false);
+        let use_pcre2 = opts.pcre2.unwrap_or(false);
+        let before_context = opts.before_context.unwrap_or(0);
+        let after_context = opts.after_context.unwrap_or(0);
+
+        if use_pcre2 && cfg!(not(feature = "pcre2")) {
+            return Err(Error::from_reason(
+                "pcre2 option requires ripgrep-napi to be built with the `pcre2` feature",
+            ));
+        }
+
+        let encoding = parse_encoding(opts.encoding.as_deref())
+            .map_err(|e| Error::from_reason(format!("Invalid encoding: {}", e)))?;
+        let binary_detection = parse_binary_detection(opts.binary_detection.as_deref())
+            .map_err(|e| Error::from_reason(format!("Invalid binary_detection: {}", e)))?;
+        let is_quit_mode = opts.binary_detection.as_deref() == Some("quit");
+        let mmap_choice = if opts.mmap.unwrap_or(true) {
+            // Safety: `MmapChoice::auto()` mmaps the searched file, so the
+            // caller must not mutate or truncate it while a search is in
+            // flight or the worker reading it can segfault/see torn data.
+            // That risk is now spread across `threads` workers (chunk0-1)
+            // instead of one, but each still only maps the single file it's
+            // actively searching, so the blast radius per worker is the same.
+            unsafe { MmapChoice::auto() }
+        } else {
+            MmapChoice::never()
+        };
+
+        let files_searched = Arc::new(AtomicU32::new(0));
+        let match_count = Arc::new(AtomicU32::new(0));
+        let binary_skipped = Arc::new(AtomicU32::new(0));
+        // Every worker builds the same pattern, so any build failure (e.g. a
+        // bad regex) is identical across threads; the first one to hit it
+        // records the message here so `search()` can still surface it as an
+        // `Err` instead of silently returning an empty result.
+        let matcher_error: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+        let (tx, rx) = mpsc::channel::<RipgrepMatch>();
+
+        let walker = walker_builder.build_parallel();
+        walker.run(|| {
+            let tx = tx.clone();
+            let pattern = pattern.clone();
+            let glob_filter = glob_filter.clone();
+            let files_searched = files_searched.clone();
+            let match_count = match_count.clone();
+            let binary_skipped = binary_skipped.clone();
+            let mmap_choice = mmap_choice.clone();
+            let matcher_error = matcher_error.clone();
+
+            // Each worker owns its own matcher and searcher so no thread
+            // ever blocks on another's I/O or matching.
+            let matcher = PatternMatcher::build(&pattern, case_insensitive, multi_line, use_pcre2);
+            if let Err(e) = &matcher {
+                matcher_error.lock().unwrap().get_or_insert_with(|| e.clone());
+            }
 
-                    // Check if we should stop
-                    if sink.stopped {
-                        break;
+            let mut searcher = SearcherBuilder::new()
+                .before_context(before_context as usize)
+                .after_context(after_context as usize)
+                .encoding(encoding.clone())
+                .binary_detection(binary_detection.clone())
+                .memory_map(mmap_choice)
+                .build();
+
+            Box::new(move |entry| {
+                let matcher = match &matcher {
+                    Ok(m) => m,
+                    Err(_) => return WalkState::Quit,
+                };
+
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(_) => return WalkState::Continue,
+                };
+
+                if !entry.file_type().is_some_and(|ft| ft.is_file()) {
+                    return WalkState::Continue;
+                }
+
+                if let Some(max) = max_results {
+                    if match_count.load(Ordering::SeqCst) >= max {
+                        return WalkState::Quit;
                     }
                 }
-            }
+
+                let path = entry.path().to_path_buf();
+
+                if !glob_filter.is_match(&path) {
+                    return WalkState::Continue;
+                }
+
+                let mut sink = MatchSink {
+                    matcher,
+                    path: path.clone(),
+                    tx: tx.clone(),
+                    max_results,
+                    match_count: match_count.clone(),
+                    binary_skipped: binary_skipped.clone(),
+                    is_quit_mode,
+                    stopped: false,
+                    pending_before: Vec::new(),
+                    pending_match: None,
+                };
+
+                let _ = searcher.search_path(matcher, &path, &mut sink);
+                files_searched.fetch_add(1, Ordering::SeqCst);
+
+                if sink.stopped {
+                    WalkState::Quit
+                } else {
+                    WalkState::Continue
+                }
+            })
+        });
+
+        // `run` has returned, so every worker's sender clone is gone; drop
+        // the original too so the receiver's iterator ends after the
+        // buffered matches.
+        drop(tx);
+        let matches: Vec<RipgrepMatch> = rx.into_iter().collect();
+
+        if let Some(err) = matcher_error.lock().unwrap().take() {
+            return Err(Error::from_reason(format!("Regex error: {}", err)));
         }
 
         let elapsed = start.elapsed();
-        let results = self.results.lock().unwrap();
-        let files = self.files_searched.lock().unwrap();
-        
-        let truncated = self.max_results.map_or(false, |max| results.len() as u32 >= max);
-        
+        let truncated = max_results.is_some_and(|max| matches.len() as u32 >= max);
+
         Ok(RipgrepResult {
-            matches: results.clone(),
-            files_searched: *files,
-            total_matches: results.len() as u32,
+            total_matches: matches.len() as u32,
+            matches,
+            files_searched: files_searched.load(Ordering::SeqCst),
             truncated,
             elapsed_ms: elapsed.as_millis() as u32,
+            binary_skipped: binary_skipped.load(Ordering::SeqCst),
         })
     }
 
@@ -175,26 +310,301 @@ impl RipgrepSearcher {
     }
 }
 
-/// Sink that collects matches
-struct MatchSink {
+/// Dispatches searches over either Rust's `regex` engine or, when the
+/// `pcre2` feature is enabled and requested, PCRE2 for lookaround and
+/// backreferences. Both implement `grep::matcher::Matcher`, so the rest of
+/// the searcher code stays oblivious to which one is active.
+enum PatternMatcher {
+    Rust(Box<grep::regex::RegexMatcher>),
+    #[cfg(feature = "pcre2")]
+    Pcre2(Box<grep_pcre2::RegexMatcher>),
+}
+
+enum PatternCaptures {
+    Rust(grep::regex::RegexCaptures),
+    #[cfg(feature = "pcre2")]
+    Pcre2(grep_pcre2::RegexCaptures),
+}
+
+impl Captures for PatternCaptures {
+    fn len(&self) -> usize {
+        match self {
+            PatternCaptures::Rust(c) => c.len(),
+            #[cfg(feature = "pcre2")]
+            PatternCaptures::Pcre2(c) => c.len(),
+        }
+    }
+
+    fn get(&self, i: usize) -> Option<Match> {
+        match self {
+            PatternCaptures::Rust(c) => c.get(i),
+            #[cfg(feature = "pcre2")]
+            PatternCaptures::Pcre2(c) => c.get(i),
+        }
+    }
+}
+
+fn matcher_err(e: impl std::fmt::Display) -> std::io::Error {
+    std::io::Error::other(e.to_string())
+}
+
+impl Matcher for PatternMatcher {
+    type Captures = PatternCaptures;
+    type Error = std::io::Error;
+
+    fn find_at(&self, haystack: &[u8], at: usize) -> std::result::Result<Option<Match>, Self::Error> {
+        match self {
+            PatternMatcher::Rust(m) => m.find_at(haystack, at).map_err(matcher_err),
+            #[cfg(feature = "pcre2")]
+            PatternMatcher::Pcre2(m) => m.find_at(haystack, at).map_err(matcher_err),
+        }
+    }
+
+    fn new_captures(&self) -> std::result::Result<Self::Captures, Self::Error> {
+        match self {
+            PatternMatcher::Rust(m) => m.new_captures().map(PatternCaptures::Rust).map_err(matcher_err),
+            #[cfg(feature = "pcre2")]
+            PatternMatcher::Pcre2(m) => m.new_captures().map(PatternCaptures::Pcre2).map_err(matcher_err),
+        }
+    }
+
+    fn capture_count(&self) -> usize {
+        match self {
+            PatternMatcher::Rust(m) => m.capture_count(),
+            #[cfg(feature = "pcre2")]
+            PatternMatcher::Pcre2(m) => m.capture_count(),
+        }
+    }
+
+    fn capture_index(&self, name: &str) -> Option<usize> {
+        match self {
+            PatternMatcher::Rust(m) => m.capture_index(name),
+            #[cfg(feature = "pcre2")]
+            PatternMatcher::Pcre2(m) => m.capture_index(name),
+        }
+    }
+}
+
+impl PatternMatcher {
+    fn build(
+        pattern: &str,
+        case_insensitive: bool,
+        multi_line: bool,
+        use_pcre2: bool,
+    ) -> std::result::Result<Self, String> {
+        if use_pcre2 {
+            #[cfg(feature = "pcre2")]
+            {
+                let mut builder = Pcre2MatcherBuilder::new();
+                builder.caseless(case_insensitive).multi_line(multi_line);
+                return builder
+                    .build(pattern)
+                    .map(|m| PatternMatcher::Pcre2(Box::new(m)))
+                    .map_err(|e| e.to_string());
+            }
+            #[cfg(not(feature = "pcre2"))]
+            {
+                return Err("pcre2 feature not enabled".to_string());
+            }
+        }
+
+        let mut builder = RegexMatcherBuilder::new();
+        builder.case_insensitive(case_insensitive).multi_line(multi_line);
+        builder
+            .build(pattern)
+            .map(|m| PatternMatcher::Rust(Box::new(m)))
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Resolves an `encoding` option to a `grep_searcher::Encoding`. `None`
+/// leaves BOM sniffing to pick the encoding per-file, falling back to UTF-8.
+fn parse_encoding(label: Option<&str>) -> std::result::Result<Option<Encoding>, String> {
+    match label {
+        None => Ok(None),
+        Some(label) => Encoding::new(label).map(Some).map_err(|e| e.to_string()),
+    }
+}
+
+/// Resolves a `binary_detection` option to the matching `BinaryDetection`
+/// heuristic. Unset mirrors ripgrep's own default of searching binary files
+/// as-is.
+fn parse_binary_detection(mode: Option<&str>) -> std::result::Result<BinaryDetection, String> {
+    match mode.unwrap_or("none") {
+        "none" => Ok(BinaryDetection::none()),
+        "quit" => Ok(BinaryDetection::quit(b'\x00')),
+        "convert" => Ok(BinaryDetection::convert(b'\x00')),
+        other => Err(format!(
+            "expected \"none\", \"quit\", or \"convert\", got \"{}\"",
+            other
+        )),
+    }
+}
+
+/// Builds an `ignore::types::Types` selector from `--type`/`--type-not`
+/// style names plus any custom `name:glob` definitions, seeded with the
+/// ~150 built-in language definitions.
+fn build_types(
+    types: &[String],
+    types_not: &[String],
+    type_defs: &[String],
+) -> std::result::Result<ignore::types::Types, ignore::Error> {
+    let mut builder = ignore::types::TypesBuilder::new();
+    builder.add_defaults();
+
+    for def in type_defs {
+        let (name, glob) = def.split_once(':').ok_or(ignore::Error::InvalidDefinition)?;
+        builder.add(name, glob)?;
+    }
+    for name in types {
+        builder.select(name);
+    }
+    for name in types_not {
+        builder.negate(name);
+    }
+
+    builder.build()
+}
+
+/// List the built-in file-type definitions available to `types`/`types_not`,
+/// so callers can populate a type picker. Custom `type_defs` are not included.
+#[napi]
+pub fn list_types() -> Result<Vec<TypeDef>> {
+    let mut builder = ignore::types::TypesBuilder::new();
+    builder.add_defaults();
+    let types = builder
+        .build()
+        .map_err(|e| Error::from_reason(format!("Failed to build type definitions: {}", e)))?;
+
+    Ok(types
+        .definitions()
+        .iter()
+        .map(|def| TypeDef {
+            name: def.name().to_string(),
+            globs: def.globs().iter().map(|g| g.to_string()).collect(),
+        })
+        .collect())
+}
+
+fn num_cpus() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+}
+
+/// Compiled `globs`/`!globs` scoping patterns, tested against the full path.
+/// A pattern containing no `/` is anchored to match at any depth and, if it
+/// names a directory, its entire subtree too (e.g. `!node_modules` excludes
+/// `node_modules/foo.js`), mirroring real ripgrep's `--glob` behavior.
+///
+/// A path is kept when it matches at least one positive glob (or there are
+/// none) and matches no negative glob.
+struct GlobFilter {
+    set: GlobSet,
+    negated: Vec<bool>,
+    has_positive: bool,
+}
+
+impl GlobFilter {
+    fn build(patterns: &[String]) -> std::result::Result<Self, globset::Error> {
+        let mut builder = GlobSetBuilder::new();
+        let mut negated = Vec::with_capacity(patterns.len());
+        let mut has_positive = false;
+
+        for pattern in patterns {
+            let (is_negated, glob_str) = match pattern.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, pattern.as_str()),
+            };
+            has_positive |= !is_negated;
+
+            // We match against the full (file) path, but a pattern with no
+            // `/` follows gitignore/ripgrep `--glob` convention: it matches
+            // at any depth, AND if it names a directory, the whole subtree
+            // under that directory is matched too (e.g. `!node_modules`
+            // must exclude `node_modules/foo.js`, not just a file literally
+            // named `node_modules`). `**/<name>` alone only covers the
+            // former, so also add `**/<name>/**` for the latter.
+            if glob_str.contains('/') {
+                let glob = GlobBuilder::new(glob_str).literal_separator(true).build()?;
+                builder.add(glob);
+                negated.push(is_negated);
+            } else {
+                for variant in [format!("**/{}", glob_str), format!("**/{}/**", glob_str)] {
+                    let glob = GlobBuilder::new(&variant).literal_separator(true).build()?;
+                    builder.add(glob);
+                    negated.push(is_negated);
+                }
+            }
+        }
+
+        Ok(Self {
+            set: builder.build()?,
+            negated,
+            has_positive,
+        })
+    }
+
+    fn is_match(&self, path: &Path) -> bool {
+        if self.negated.is_empty() {
+            return true;
+        }
+
+        let matched: Vec<usize> = self.set.matches(path);
+        if matched.iter().any(|&i| self.negated[i]) {
+            return false;
+        }
+        if self.has_positive {
+            return matched.iter().any(|&i| !self.negated[i]);
+        }
+        true
+    }
+}
+
+/// Sink that streams matches back to the main thread over a channel.
+///
+/// Context lines arrive via separate `context()` calls interleaved around
+/// `matched()`, so a match is held in `pending_match` until we know no more
+/// `after_context` lines are coming for it (the next match, a
+/// `context_break`, or end of file) before it's sent.
+struct MatchSink<'m> {
+    matcher: &'m PatternMatcher,
     path: PathBuf,
-    results: Arc<Mutex<Vec<RipgrepMatch>>>,
+    tx: mpsc::Sender<RipgrepMatch>,
     max_results: Option<u32>,
+    match_count: Arc<AtomicU32>,
+    binary_skipped: Arc<AtomicU32>,
+    /// Whether `binary_detection` is `"quit"`, i.e. whether `binary_data`
+    /// should count this file against `binary_skipped`.
+    is_quit_mode: bool,
     stopped: bool,
+    pending_before: Vec<ContextLine>,
+    pending_match: Option<RipgrepMatch>,
 }
 
-impl Sink for MatchSink {
-    type Match = ();
+impl<'m> MatchSink<'m> {
+    fn flush_pending(&mut self) {
+        if let Some(m) = self.pending_match.take() {
+            let _ = self.tx.send(m);
+        }
+    }
+}
+
+impl<'m> Sink for MatchSink<'m> {
+    type Error = std::io::Error;
 
     fn matched(
         &mut self,
         _searcher: &Searcher,
         mat: &SinkMatch,
-    ) -> Result<bool, grep_searcher::Error> {
+    ) -> std::result::Result<bool, std::io::Error> {
+        // The previous match's after_context is complete now that a new
+        // match has arrived.
+        self.flush_pending();
+
         // Check if we've hit max results
         if let Some(max) = self.max_results {
-            let results = self.results.lock().unwrap();
-            if results.len() as u32 >= max {
+            if self.match_count.load(Ordering::SeqCst) >= max {
                 self.stopped = true;
                 return Ok(false);
             }
@@ -202,40 +612,85 @@ impl Sink for MatchSink {
 
         let line = String::from_utf8_lossy(mat.lines().next().unwrap_or(&[]));
         let mut submatches = Vec::new();
-        
-        for m in mat.matches() {
+        self.matcher.find_iter(mat.bytes(), |m| {
             submatches.push(SubMatch {
                 start: m.start() as u32,
                 end: m.end() as u32,
             });
-        }
+            true
+        })?;
 
         let match_entry = RipgrepMatch {
             path: self.path.to_string_lossy().to_string(),
-            line: mat.absolute_line_number(),
+            line: mat.line_number().unwrap_or(0) as u32,
             column: submatches.first().map(|s| s.start).unwrap_or(0),
             text: line.to_string(),
             submatches,
+            context_before: std::mem::take(&mut self.pending_before),
+            context_after: Vec::new(),
+        };
+
+        self.match_count.fetch_add(1, Ordering::SeqCst);
+        self.pending_match = Some(match_entry);
+
+        Ok(!self.stopped)
+    }
+
+    fn context(
+        &mut self,
+        _searcher: &Searcher,
+        ctx: &SinkContext,
+    ) -> std::result::Result<bool, std::io::Error> {
+        let context_line = ContextLine {
+            line_number: ctx.line_number().unwrap_or(0) as u32,
+            text: String::from_utf8_lossy(ctx.bytes()).into_owned(),
         };
 
-        {
-            let mut results = self.results.lock().unwrap();
-            results.push(match_entry);
+        match ctx.kind() {
+            SinkContextKind::Before => self.pending_before.push(context_line),
+            SinkContextKind::After => {
+                if let Some(m) = self.pending_match.as_mut() {
+                    m.context_after.push(context_line);
+                }
+            }
+            SinkContextKind::Other => {}
         }
 
         Ok(!self.stopped)
     }
-}
 
-/// Simple glob matching
-fn glob_match(pattern: &str, text: &str) -> bool {
-    let regex_pattern = pattern
-        .replace('.', "\\.")
-        .replace('*', ".*")
-        .replace('?', ".");
-    
-    let re = regex::Regex::new(&format!("^{}$", regex_pattern)).unwrap();
-    re.is_match(text)
+    fn context_break(&mut self, _searcher: &Searcher) -> std::result::Result<bool, std::io::Error> {
+        // A gap in context means the previous match's after_context is done
+        // and any buffered before_context belongs to no one.
+        self.flush_pending();
+        self.pending_before.clear();
+        Ok(!self.stopped)
+    }
+
+    fn binary_data(
+        &mut self,
+        _searcher: &Searcher,
+        _binary_byte_offset: u64,
+    ) -> std::result::Result<bool, std::io::Error> {
+        // The searcher's own `BinaryDetection` mode already decides whether
+        // to stop on a binary byte (`quit`) or keep going with it replaced
+        // (`convert`) — returning `false` here would force a stop either
+        // way, so always defer to that and only use this callback to count
+        // the `quit` case.
+        if self.is_quit_mode {
+            self.binary_skipped.fetch_add(1, Ordering::SeqCst);
+        }
+        Ok(true)
+    }
+
+    fn finish(
+        &mut self,
+        _searcher: &Searcher,
+        _finish: &SinkFinish,
+    ) -> std::result::Result<(), std::io::Error> {
+        self.flush_pending();
+        Ok(())
+    }
 }
 
 /// Convenience function for searching
@@ -256,3 +711,117 @@ pub async fn search_files(opts: RipgrepOptions) -> Result<Vec<String>> {
     files.dedup();
     Ok(files)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slash_free_glob_matches_at_any_depth() {
+        let filter = GlobFilter::build(&["*.rs".to_string()]).unwrap();
+        assert!(filter.is_match(Path::new("lib.rs")));
+        assert!(filter.is_match(Path::new("src/lib.rs")));
+        assert!(filter.is_match(Path::new("src/native/ripgrep-napi/src/lib.rs")));
+        assert!(!filter.is_match(Path::new("src/lib.js")));
+    }
+
+    #[test]
+    fn glob_with_slash_is_matched_as_given() {
+        let filter = GlobFilter::build(&["src/*.rs".to_string()]).unwrap();
+        assert!(filter.is_match(Path::new("src/lib.rs")));
+        assert!(!filter.is_match(Path::new("src/native/lib.rs")));
+    }
+
+    #[test]
+    fn negated_glob_excludes_matching_paths() {
+        let filter =
+            GlobFilter::build(&["*.rs".to_string(), "!**/tests/**".to_string()]).unwrap();
+        assert!(filter.is_match(Path::new("src/lib.rs")));
+        assert!(!filter.is_match(Path::new("src/tests/lib.rs")));
+    }
+
+    #[test]
+    fn slash_free_negated_glob_excludes_directory_contents() {
+        let filter = GlobFilter::build(&["!node_modules".to_string()]).unwrap();
+        assert!(!filter.is_match(Path::new("node_modules/foo.js")));
+        assert!(!filter.is_match(Path::new("src/node_modules/foo.js")));
+        assert!(!filter.is_match(Path::new("src/node_modules/nested/bar.js")));
+        assert!(filter.is_match(Path::new("src/lib.rs")));
+    }
+
+    #[test]
+    fn no_globs_matches_everything() {
+        let filter = GlobFilter::build(&[]).unwrap();
+        assert!(filter.is_match(Path::new("anything/at/all.txt")));
+    }
+
+    /// Drives `MatchSink` with a real `Searcher`/`RegexMatcher` over two
+    /// matches separated by enough unmatched lines to force a
+    /// `context_break`, to pin down the `pending_before`/`pending_match`
+    /// buffering: each match should get only its own surrounding lines, not
+    /// the other match's.
+    #[test]
+    fn context_buffering_resets_between_matches() {
+        let matcher =
+            PatternMatcher::Rust(Box::new(RegexMatcherBuilder::new().build("ERROR").unwrap()));
+        let mut searcher = SearcherBuilder::new()
+            .before_context(1)
+            .after_context(1)
+            .build();
+
+        let text = b"line1\nERROR one\nline3\nline4\nline5\nERROR two\nline7\n";
+        let (tx, rx) = mpsc::channel::<RipgrepMatch>();
+        let mut sink = MatchSink {
+            matcher: &matcher,
+            path: PathBuf::from("test.txt"),
+            tx: tx.clone(),
+            max_results: None,
+            match_count: Arc::new(AtomicU32::new(0)),
+            binary_skipped: Arc::new(AtomicU32::new(0)),
+            is_quit_mode: false,
+            stopped: false,
+            pending_before: Vec::new(),
+            pending_match: None,
+        };
+
+        searcher.search_slice(&matcher, text, &mut sink).unwrap();
+        drop(tx);
+        drop(sink);
+        let matches: Vec<RipgrepMatch> = rx.into_iter().collect();
+
+        assert_eq!(matches.len(), 2);
+
+        assert_eq!(matches[0].context_before.len(), 1);
+        assert_eq!(matches[0].context_before[0].text.trim_end(), "line1");
+        assert_eq!(matches[0].context_after.len(), 1);
+        assert_eq!(matches[0].context_after[0].text.trim_end(), "line3");
+
+        assert_eq!(matches[1].context_before.len(), 1);
+        assert_eq!(matches[1].context_before[0].text.trim_end(), "line5");
+        assert_eq!(matches[1].context_after.len(), 1);
+        assert_eq!(matches[1].context_after[0].text.trim_end(), "line7");
+    }
+
+    #[test]
+    fn pattern_matcher_build_rust_engine_succeeds() {
+        let matcher = PatternMatcher::build("ERROR", false, false, false).unwrap();
+        assert!(matches!(matcher, PatternMatcher::Rust(_)));
+    }
+
+    #[test]
+    fn pattern_matcher_build_pcre2_without_feature_errors() {
+        if cfg!(feature = "pcre2") {
+            return;
+        }
+        match PatternMatcher::build("ERROR", false, false, true) {
+            Err(e) => assert!(e.contains("pcre2")),
+            Ok(_) => panic!("expected an error when pcre2 is requested without the feature"),
+        }
+    }
+
+    #[test]
+    fn build_types_rejects_malformed_type_def() {
+        let err = build_types(&[], &[], &["not-a-valid-def".to_string()]).unwrap_err();
+        assert!(matches!(err, ignore::Error::InvalidDefinition));
+    }
+}